@@ -0,0 +1,209 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use http::{HeaderMap, HeaderValue, StatusCode, header};
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+
+/// An on-disk cache of conditional-GET responses, keyed by URL.
+///
+/// Lives next to the SQLite store so a long-running sync can reuse unchanged
+/// response bodies across restarts instead of refetching them.
+struct HttpCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    etag: Option<String>,
+    body: String,
+}
+
+impl HttpCache {
+    fn new(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    fn get(&self, url: &str) -> Option<CacheEntry> {
+        let raw = std::fs::read_to_string(self.path(url)).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    fn put(&self, url: &str, entry: &CacheEntry) -> std::io::Result<()> {
+        let raw = serde_json::to_string(entry).expect("cache entry serializes");
+        std::fs::write(self.path(url), raw)
+    }
+}
+
+/// A page of a (possibly paginated) response.
+pub struct CachedPage {
+    /// The response body, served from cache on a `304 Not Modified`.
+    pub body: String,
+    /// Whether the response was a `304 Not Modified`.
+    pub not_modified: bool,
+    /// The `rel="next"` link, when the response is paginated.
+    pub next: Option<String>,
+}
+
+/// A thin GitHub client that caches per-URL ETags and bodies and backs off when
+/// the API reports the rate limit is exhausted.
+pub struct CachedClient {
+    client: Client,
+    cache: HttpCache,
+}
+
+impl CachedClient {
+    pub fn new(token: &str, cache_dir: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        headers.insert(header::USER_AGENT, HeaderValue::from_static("yousync"));
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {token}"))?,
+        );
+
+        Ok(Self {
+            client: Client::builder().default_headers(headers).build()?,
+            cache: HttpCache::new(cache_dir)?,
+        })
+    }
+
+    /// Perform a conditional GET, serving the cached body on `304` and honouring
+    /// the rate-limit headers before returning.
+    pub async fn get(&self, url: &str) -> anyhow::Result<CachedPage> {
+        let cached = self.cache.get(url);
+
+        let mut request = self.client.get(url);
+        if let Some(etag) = cached.as_ref().and_then(|entry| entry.etag.as_deref()) {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+
+        let response = request.send().await.with_context(|| "GitHub request failed")?;
+        let status = response.status();
+        let next = next_link(response.headers());
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let backoff = rate_limit_backoff(response.headers());
+
+        let page = if status == StatusCode::NOT_MODIFIED {
+            let entry = cached.with_context(|| "received 304 without a cached body")?;
+            CachedPage {
+                body: entry.body,
+                not_modified: true,
+                next,
+            }
+        } else {
+            let body = response.text().await?;
+            // Never cache or return an error body as if it were data — doing so
+            // silently turns a failed request into "zero issues".
+            if !status.is_success() {
+                anyhow::bail!("GitHub request to {url} failed with {status}: {body}");
+            }
+            self.cache.put(
+                url,
+                &CacheEntry {
+                    etag,
+                    body: body.clone(),
+                },
+            )?;
+            CachedPage {
+                body,
+                not_modified: false,
+                next,
+            }
+        };
+
+        if let Some(backoff) = backoff {
+            println!("Rate limited, backing off for {}s", backoff.as_secs());
+            tokio::time::sleep(backoff).await;
+        }
+
+        Ok(page)
+    }
+
+    /// Walk every page of a paginated resource, concatenating the items.
+    pub async fn get_all(&self, url: &str) -> anyhow::Result<String> {
+        let mut pages = Vec::new();
+        let mut next = Some(url.to_string());
+        while let Some(url) = next {
+            let page = self.get(&url).await?;
+            pages.push(page.body);
+            next = page.next;
+        }
+
+        Ok(merge_json_arrays(&pages))
+    }
+}
+
+/// How long to sleep given a response's rate-limit headers, if at all.
+fn rate_limit_backoff(headers: &HeaderMap) -> Option<Duration> {
+    // A `Retry-After` always takes precedence.
+    if let Some(secs) = header_u64(headers, "retry-after") {
+        return Some(Duration::from_secs(secs));
+    }
+
+    if header_u64(headers, "x-ratelimit-remaining") == Some(0) {
+        let reset = header_u64(headers, "x-ratelimit-reset")?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        return Some(Duration::from_secs(reset.saturating_sub(now)));
+    }
+
+    None
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.parse().ok()
+}
+
+/// Extract the `rel="next"` URL from a `Link` header, if present.
+fn next_link(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(header::LINK)?.to_str().ok()?;
+    for part in link.split(',') {
+        if part.contains("rel=\"next\"") {
+            let start = part.find('<')? + 1;
+            let end = part.find('>')?;
+            return Some(part[start..end].to_string());
+        }
+    }
+    None
+}
+
+/// Concatenate several JSON arrays (one per page) into a single array.
+fn merge_json_arrays(pages: &[String]) -> String {
+    let mut merged = Vec::new();
+    for page in pages {
+        if let Ok(serde_json::Value::Array(items)) = serde_json::from_str(page) {
+            merged.extend(items);
+        }
+    }
+    serde_json::Value::Array(merged).to_string()
+}
+
+/// Build an absolute GitHub API URL from a path-and-query.
+pub fn api_url(path: &str) -> anyhow::Result<Url> {
+    Url::parse("https://api.github.com")?
+        .join(path)
+        .with_context(|| "Failed to build a GitHub API URL")
+}
@@ -1,28 +1,45 @@
 use std::{
     collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
     time::Duration,
 };
 
 use anyhow::Context;
-use clap::Parser;
-use http::{HeaderMap, StatusCode, Uri};
-use octocrab::{
-    FromResponse, Octocrab, Page,
-    etag::EntityTag,
-    models::{
-        self, IssueEventId, IssueState,
-        issues::{Issue, IssueStateReason},
-    },
+use axum::{
+    Router,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap as AxumHeaderMap, StatusCode as AxumStatusCode},
+    routing::post,
 };
+use clap::{Parser, Subcommand, ValueEnum};
+use hmac::{Hmac, Mac};
+use octocrab::models::issues::Issue;
 use reqwest::Url;
 use serde::Deserialize;
-use youtrack_api::{CustomField, IssueData, StateBundleElement, YouTrack};
+use sha2::Sha256;
+use tokio::sync::Mutex;
+use youtrack_api::YouTrack;
+
+use crate::{
+    cache::CachedClient,
+    mapping::Mapping,
+    source::{EventCursor, GitHubSource, GitLabSource, IssueSource, SourceEvent, SourceIssue},
+    store::Store,
+};
+
+mod cache;
+mod mapping;
+mod source;
+mod store;
 
 #[derive(Parser)]
 #[command(name = "yousync")]
 #[command(about = "A tool for synchronisation between GitHub and YouTrack")]
 pub struct Args {
-    /// Owner of the repository
+    /// Owner of the repository (group/project for GitLab)
     owner: String,
     /// Name of the repository
     repo: String,
@@ -30,67 +47,97 @@ pub struct Args {
     youtrack: Url,
     /// Project name (query)
     project: String,
+    /// Issue source to mirror from
+    #[arg(long, value_enum, default_value_t = Source::Github)]
+    source: Source,
+    /// GitLab host (required when `--source gitlab`)
+    #[arg(long)]
+    gitlab_host: Option<Url>,
+    /// Path to the SQLite store holding the issue mapping and poll cursor
+    #[arg(long, default_value = "yousync.db")]
+    db_path: PathBuf,
+    /// Path to a JSON field-mapping file (defaults to syncing only `State`)
+    #[arg(long)]
+    mapping: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(ValueEnum, Clone, PartialEq, Eq)]
+pub enum Source {
+    Github,
+    Gitlab,
 }
 
-async fn get_issues(octocrab: &Octocrab, args: &Args) -> anyhow::Result<Vec<Issue>> {
-    println!("Fetching issues from the repository...");
+#[derive(Subcommand, Clone)]
+pub enum Command {
+    /// Poll the source for issue events on a fixed interval (default)
+    Poll,
+    /// Receive GitHub `issues` webhooks over HTTP in real time
+    Serve {
+        /// Address to bind the webhook receiver to
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: SocketAddr,
+    },
+}
 
-    let mut page = octocrab
-        .issues(&args.owner, &args.repo)
-        .list()
-        .state(octocrab::params::State::All)
-        .per_page(100)
-        .send()
-        .await?;
+/// Build the issue source selected on the command line.
+async fn build_source(args: &Args) -> anyhow::Result<Box<dyn IssueSource>> {
+    match args.source {
+        Source::Github => {
+            let token = std::env::var("YOUSYNC_GITHUB_TOKEN")
+                .map(Ok)
+                .unwrap_or_else(|err| {
+                    println!("{err}");
+                    rpassword::prompt_password("GitHub Token: ")
+                })?;
 
-    let mut issues = Vec::new();
+            let octocrab = octocrab::instance().user_access_token(token.clone())?;
 
-    loop {
-        issues.extend(page.take_items());
-        page = match octocrab
-            .get_page::<models::issues::Issue>(&page.next)
-            .await?
-        {
-            Some(v) => v,
-            None => break,
-        };
-    }
+            // Cache conditional GETs on disk next to the SQLite store.
+            let cache_dir = args
+                .db_path
+                .parent()
+                .unwrap_or_else(|| std::path::Path::new("."))
+                .join("yousync-cache");
+            let cache = CachedClient::new(&token, cache_dir)?;
 
-    Ok(issues)
+            Ok(Box::new(GitHubSource::new(
+                octocrab,
+                cache,
+                args.owner.clone(),
+                args.repo.clone(),
+            )?))
+        }
+        Source::Gitlab => {
+            let host = args
+                .gitlab_host
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("--gitlab-host is required for the gitlab source"))?;
+            let token = std::env::var("YOUSYNC_GITLAB_TOKEN")
+                .map(Ok)
+                .unwrap_or_else(|err| {
+                    println!("{err}");
+                    rpassword::prompt_password("GitLab Token: ")
+                })?;
+
+            Ok(Box::new(GitLabSource::new(
+                host,
+                format!("{}/{}", args.owner, args.repo),
+                &token,
+            )?))
+        }
+    }
 }
 
 // Different main so that result can be returned
 async fn run() -> anyhow::Result<()> {
     let args = Args::parse();
 
-    // Get github token
-    let token = std::env::var("YOUSYNC_GITHUB_TOKEN")
-        .map(Ok)
-        .unwrap_or_else(|err| {
-            println!("{err}");
-            rpassword::prompt_password("GitHub Token: ")
-        })?;
-
-    let octocrab = octocrab::instance().user_access_token(token)?;
-
-    // Fetch events for etag
-    let issues_uri = format!(
-        "/repos/{}/{}/issues/events?per_page=10",
-        args.owner, args.repo
-    );
-    let issues_uri = Uri::builder()
-        .scheme("https")
-        .authority("api.github.com")
-        .path_and_query(issues_uri)
-        .build()?;
-    println!("{issues_uri}");
-    let response = octocrab._get(&issues_uri).await?;
+    let source = build_source(&args).await?;
 
-    let mut etag = EntityTag::extract_from_response(&response);
-
-    let github_issues = get_issues(&octocrab, &args).await?;
-
-    println!("Found {} issues", github_issues.len());
+    let source_issues = source.list_issues().await?;
+    println!("Found {} issues", source_issues.len());
 
     // Get youtrack token
     let token = std::env::var("YOUSYNC_YOUTRACK_TOKEN")
@@ -100,123 +147,261 @@ async fn run() -> anyhow::Result<()> {
             rpassword::prompt_password("YouTrack Token: ")
         })?;
 
-    let youtrack = YouTrack::new(args.youtrack, token)?;
+    let youtrack = YouTrack::new(args.youtrack.clone(), token)?;
 
     let projects = youtrack.find_project(&args.project).await.unwrap();
 
     let project = projects
-        .first()
+        .into_iter()
+        .next()
         .ok_or_else(|| anyhow::anyhow!("Project not found"))?;
 
     println!("Project found: {}", project.name());
 
-    let mut issues = HashMap::new();
-    for issue in github_issues.iter().filter(|v| v.pull_request.is_none()) {
-        let id = create_issue(project, issue).await?;
+    // Load the field mapping driving how issues are written to YouTrack.
+    let mapping = match &args.mapping {
+        Some(path) => {
+            let raw =
+                std::fs::read_to_string(path).with_context(|| "Failed to read the mapping file")?;
+            serde_json::from_str(&raw).with_context(|| "Failed to parse the mapping file")?
+        }
+        None => Mapping::default(),
+    };
+
+    // Resume from the persisted mapping so restarts don't duplicate issues.
+    let store = Store::open(&args.db_path).with_context(|| "Failed to open the store")?;
+    let mut issues = store.load_mapping()?;
+
+    for issue in source_issues.iter().filter(|v| !v.is_pull_request) {
+        if issues.contains_key(&issue.id) {
+            continue;
+        }
 
+        let id = create_issue(&project, &mapping, issue).await?;
+        store.insert_mapping(issue.id, &id)?;
         issues.insert(issue.id, id);
     }
 
-    // Ok(())
+    match args.command.clone().unwrap_or(Command::Poll) {
+        Command::Poll => poll_loop(source.as_ref(), &project, &store, &mapping, issues).await,
+        Command::Serve { addr } => serve(project, store, mapping, issues, addr).await,
+    }
+}
 
-    // Start syncing events
+/// Poll the source for events, persisting the cursor so restarts resume where
+/// they left off.
+async fn poll_loop(
+    source: &dyn IssueSource,
+    project: &youtrack_api::Project,
+    store: &Store,
+    mapping: &Mapping,
+    mut issues: HashMap<u64, youtrack_api::IssueId>,
+) -> anyhow::Result<()> {
     println!("Sync active");
-    let mut seen = VecDeque::with_capacity(20);
+    let mut cursor = EventCursor {
+        position: store.cursor()?,
+    };
+    let mut seen: VecDeque<_> = store.load_seen()?.into_iter().collect();
+
     loop {
-        let response = octocrab
-            ._get_with_headers(
-                &issues_uri,
-                Some({
-                    let mut map = HeaderMap::new();
-                    if let Some(etag) = etag {
-                        EntityTag::insert_if_none_match_header(&mut map, etag)?;
-                    }
-                    map
-                }),
-            )
-            .await
-            .with_context(|| "Failed to fetch events with etag")?;
-        etag = EntityTag::extract_from_response(&response);
-
-        if response.status() != StatusCode::NOT_MODIFIED {
-            let page: Page<AltEvent> = Page::from_response(response)
-                .await
-                .with_context(|| "Failed to parse response")?;
-            for event in page {
-                if !seen.contains(&event.id) {
-                    if seen.len() == 20 {
-                        seen.pop_front();
-                    }
-
-                    seen.push_front(event.id);
-                    println!("Event {:?}", event.id);
-
-                    // Update the youtrack issue if it changes.
-                    // Create the issue if it doesn't already exist
-                    // This won't create new issues the moment they're created
-                    // (unless github automatically raises a relevant event that I haven't found).
-                    // According to the docs, a field `action` with value `opened` should be available
-                    // but that doesn't appear to be the case. The problem statement only refers to
-                    // updating existing issues, though, so it should be fine.
-                    if let Some(youtrack_id) = issues.get(&event.issue.id) {
-                        if event.event == "closed"
-                            || event.event == "reopened"
-                            || event.event == "renamed"
-                        {
-                            project
-                                .update_issue(youtrack_id, &create_issue_data(&event.issue))
-                                .await?;
-                        }
-                    } else {
-                        create_issue(project, &event.issue).await?;
-                    }
-                }
+        let events = source.poll_events(&mut cursor).await?;
+        if let Some(position) = &cursor.position {
+            store.set_cursor(position)?;
+        }
+
+        for event in events {
+            if seen.contains(&event.id) {
+                continue;
             }
+            if seen.len() == 20 {
+                seen.pop_front();
+            }
+            seen.push_back(event.id);
+            println!("Event {}", event.id);
+
+            handle_event(project, store, mapping, &mut issues, &event).await?;
         }
+        seen.make_contiguous();
+        store.set_seen(seen.as_slices().0)?;
 
-        std::thread::sleep(Duration::from_secs(1));
+        tokio::time::sleep(Duration::from_secs(1)).await;
     }
 }
 
-async fn create_issue(
-    project: &youtrack_api::Project,
-    issue: &Issue,
-) -> Result<youtrack_api::IssueId, anyhow::Error> {
-    project
-        .create_issue(project.id().clone(), &create_issue_data(issue))
-        .await
-        .with_context(|| "Failed to create an issue")
+/// Shared state for the webhook receiver.
+#[derive(Clone)]
+struct WebhookState {
+    project: Arc<youtrack_api::Project>,
+    store: Arc<Mutex<Store>>,
+    mapping: Arc<Mapping>,
+    issues: Arc<Mutex<HashMap<u64, youtrack_api::IssueId>>>,
+    secret: Arc<Vec<u8>>,
+}
+
+/// Start an HTTP server exposing `/webhook` for GitHub `issues` deliveries.
+///
+/// Unlike the polling path this also observes `opened` actions, which the
+/// events API does not surface.
+async fn serve(
+    project: youtrack_api::Project,
+    store: Store,
+    mapping: Mapping,
+    issues: HashMap<u64, youtrack_api::IssueId>,
+    addr: SocketAddr,
+) -> anyhow::Result<()> {
+    let secret = std::env::var("YOUSYNC_WEBHOOK_SECRET")
+        .with_context(|| "YOUSYNC_WEBHOOK_SECRET must be set to verify webhook deliveries")?;
+
+    let state = WebhookState {
+        project: Arc::new(project),
+        store: Arc::new(Mutex::new(store)),
+        mapping: Arc::new(mapping),
+        issues: Arc::new(Mutex::new(issues)),
+        secret: Arc::new(secret.into_bytes()),
+    };
+
+    let app = Router::new()
+        .route("/webhook", post(webhook))
+        .with_state(state);
+
+    println!("Listening for webhooks on {addr}");
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+/// A GitHub `issues` or `issue_comment` webhook payload.
+#[derive(Deserialize)]
+struct WebhookPayload {
+    action: String,
+    issue: Issue,
+    /// Present on `issue_comment` deliveries.
+    comment: Option<CommentPayload>,
 }
 
-fn create_issue_data(issue: &Issue) -> IssueData {
-    IssueData {
-        summary: issue.title.clone(),
-        description: issue.body.clone(),
-        custom_fields: vec![CustomField {
-            name: "State".to_string(),
-            type_: "StateIssueCustomField".to_string(),
-            value: StateBundleElement {
-                name: match issue.state {
-                    IssueState::Open => "Open",
-                    IssueState::Closed => match issue.state_reason {
-                        Some(IssueStateReason::NotPlanned) => "Won't fix",
-                        Some(IssueStateReason::Reopened) => "Reopened",
-                        Some(IssueStateReason::Duplicate) => "Duplicate",
-                        _ => "Fixed",
-                    },
-                    _ => "Submitted",
+#[derive(Deserialize)]
+struct CommentPayload {
+    body: Option<String>,
+}
+
+async fn webhook(
+    State(state): State<WebhookState>,
+    headers: AxumHeaderMap,
+    body: Bytes,
+) -> AxumStatusCode {
+    let Some(signature) = headers
+        .get("X-Hub-Signature-256")
+        .and_then(|v| v.to_str().ok())
+    else {
+        return AxumStatusCode::UNAUTHORIZED;
+    };
+
+    // Verify authenticity over the exact received bytes, before parsing.
+    if !verify_signature(&state.secret, &body, signature) {
+        return AxumStatusCode::UNAUTHORIZED;
+    }
+
+    let payload: WebhookPayload = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(err) => {
+            println!("Failed to parse webhook payload: {err}");
+            return AxumStatusCode::BAD_REQUEST;
+        }
+    };
+
+    println!("Webhook {} #{}", payload.action, payload.issue.number);
+
+    // A newly created comment arrives as an `issue_comment` delivery; turn it
+    // into a `commented` event so it's mirrored like the poll path.
+    let event = match payload.comment {
+        Some(comment) if payload.action == "created" => SourceEvent {
+            id: payload.issue.id.0,
+            action: "commented".to_string(),
+            issue: SourceIssue::from(payload.issue),
+            comment: comment.body,
+        },
+        _ => SourceEvent {
+            id: payload.issue.id.0,
+            action: payload.action,
+            issue: SourceIssue::from(payload.issue),
+            comment: None,
+        },
+    };
+
+    let store = state.store.lock().await;
+    let mut issues = state.issues.lock().await;
+    if let Err(err) = handle_event(&state.project, &store, &state.mapping, &mut issues, &event).await
+    {
+        println!("Failed to handle webhook: {err}");
+        return AxumStatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    AxumStatusCode::OK
+}
+
+/// Verify a `sha256=<hex>` signature against the HMAC-SHA256 of `body`.
+///
+/// The comparison is constant time, courtesy of [`Mac::verify_slice`].
+fn verify_signature(secret: &[u8], body: &[u8], signature: &str) -> bool {
+    let Some(hex) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Ok(expected) = hex::decode(hex) else {
+        return false;
+    };
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Create or update the YouTrack issue mirroring a source issue, depending on
+/// whether we have already seen it.
+async fn handle_event(
+    project: &youtrack_api::Project,
+    store: &Store,
+    mapping: &Mapping,
+    issues: &mut HashMap<u64, youtrack_api::IssueId>,
+    event: &SourceEvent,
+) -> anyhow::Result<()> {
+    // Create the issue if it doesn't already exist, otherwise reflect the
+    // change a relevant action describes.
+    if let Some(youtrack_id) = issues.get(&event.issue.id) {
+        match event.action.as_str() {
+            // Title, state and labels all live in the issue payload, so a full
+            // rewrite keeps the mirror faithful.
+            "closed" | "reopened" | "renamed" | "edited" | "labeled" | "unlabeled" => {
+                project
+                    .update_issue(youtrack_id, &mapping.issue_data(&event.issue))
+                    .await?;
+            }
+            // Comments are carried out-of-band by the source.
+            "commented" => {
+                if let Some(body) = &event.comment {
+                    project.add_comment(youtrack_id, body).await?;
                 }
-                .to_string(),
-            },
-        }],
+            }
+            _ => {}
+        }
+    } else {
+        let id = create_issue(project, mapping, &event.issue).await?;
+        store.insert_mapping(event.issue.id, &id)?;
+        issues.insert(event.issue.id, id);
     }
+
+    Ok(())
 }
 
-#[derive(Deserialize)]
-struct AltEvent {
-    id: IssueEventId,
-    issue: Issue, // r#type: EventType,
-    event: String,
+async fn create_issue(
+    project: &youtrack_api::Project,
+    mapping: &Mapping,
+    issue: &SourceIssue,
+) -> Result<youtrack_api::IssueId, anyhow::Error> {
+    project
+        .create_issue(project.id().clone(), &mapping.issue_data(issue))
+        .await
+        .with_context(|| "Failed to create an issue")
 }
 
 #[tokio::main]
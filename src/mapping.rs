@@ -0,0 +1,147 @@
+use serde::Deserialize;
+use youtrack_api::{
+    CustomField, CustomFieldValue, EnumBundleElement, IssueData, SingleUserValue,
+    StateBundleElement,
+};
+
+use crate::source::{CloseReason, SourceIssue, SourceState};
+
+/// Declares how GitHub issue attributes map onto YouTrack custom fields.
+///
+/// Loaded from the `--mapping` file so users on differently-configured YouTrack
+/// projects can retarget fields without patching source. An empty mapping
+/// reproduces the historical behaviour of syncing only the `State` field.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Mapping {
+    /// Field the GitHub open/closed state maps onto.
+    pub state: Option<StateField>,
+    /// Multi-value enum field the GitHub labels map onto.
+    pub labels: Option<FieldConfig>,
+    /// User field the GitHub assignee maps onto.
+    pub assignee: Option<FieldConfig>,
+    /// Text field the GitHub milestone maps onto.
+    pub milestone: Option<FieldConfig>,
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Self {
+            state: Some(StateField::default()),
+            labels: None,
+            assignee: None,
+            milestone: None,
+        }
+    }
+}
+
+/// A target YouTrack custom field: its name and `$type`.
+#[derive(Deserialize)]
+pub struct FieldConfig {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+}
+
+/// The state field together with the (per-project overridable) table mapping
+/// GitHub states and close reasons onto YouTrack state names.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct StateField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub open: String,
+    pub not_planned: String,
+    pub reopened: String,
+    pub duplicate: String,
+    pub fixed: String,
+    pub other: String,
+}
+
+impl Default for StateField {
+    fn default() -> Self {
+        Self {
+            name: "State".to_string(),
+            type_: "StateIssueCustomField".to_string(),
+            open: "Open".to_string(),
+            not_planned: "Won't fix".to_string(),
+            reopened: "Reopened".to_string(),
+            duplicate: "Duplicate".to_string(),
+            fixed: "Fixed".to_string(),
+            other: "Submitted".to_string(),
+        }
+    }
+}
+
+impl StateField {
+    fn state_name(&self, issue: &SourceIssue) -> &str {
+        match issue.state {
+            SourceState::Open => &self.open,
+            SourceState::Closed => match issue.close_reason {
+                Some(CloseReason::NotPlanned) => &self.not_planned,
+                Some(CloseReason::Reopened) => &self.reopened,
+                Some(CloseReason::Duplicate) => &self.duplicate,
+                _ => &self.fixed,
+            },
+            _ => &self.other,
+        }
+    }
+}
+
+impl Mapping {
+    /// Build the YouTrack issue payload for a source issue per this mapping.
+    pub fn issue_data(&self, issue: &SourceIssue) -> IssueData {
+        let mut custom_fields = Vec::new();
+
+        if let Some(state) = &self.state {
+            custom_fields.push(CustomField {
+                name: state.name.clone(),
+                type_: state.type_.clone(),
+                value: CustomFieldValue::State(StateBundleElement {
+                    name: state.state_name(issue).to_string(),
+                }),
+            });
+        }
+
+        if let Some(field) = &self.labels {
+            custom_fields.push(CustomField {
+                name: field.name.clone(),
+                type_: field.type_.clone(),
+                value: CustomFieldValue::MultiEnum(
+                    issue
+                        .labels
+                        .iter()
+                        .map(|label| EnumBundleElement { name: label.clone() })
+                        .collect(),
+                ),
+            });
+        }
+
+        if let Some(field) = &self.assignee {
+            if let Some(assignee) = &issue.assignee {
+                custom_fields.push(CustomField {
+                    name: field.name.clone(),
+                    type_: field.type_.clone(),
+                    value: CustomFieldValue::User(SingleUserValue::new(assignee.clone())),
+                });
+            }
+        }
+
+        if let Some(field) = &self.milestone {
+            if let Some(milestone) = &issue.milestone {
+                custom_fields.push(CustomField {
+                    name: field.name.clone(),
+                    type_: field.type_.clone(),
+                    value: CustomFieldValue::Text(milestone.clone()),
+                });
+            }
+        }
+
+        IssueData {
+            summary: issue.title.clone(),
+            description: issue.body.clone(),
+            custom_fields,
+        }
+    }
+}
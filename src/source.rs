@@ -0,0 +1,352 @@
+use std::{
+    collections::{HashSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use http::HeaderMap;
+use octocrab::{
+    Octocrab,
+    models::{IssueState, issues::Issue, issues::IssueStateReason},
+};
+use reqwest::{Client, Url};
+use serde::Deserialize;
+
+use crate::cache::{CachedClient, api_url};
+
+/// A backend-neutral issue, produced by every [`IssueSource`] and consumed by
+/// the YouTrack-writing code so that code is agnostic to where issues come
+/// from.
+pub struct SourceIssue {
+    pub id: u64,
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    pub state: SourceState,
+    pub close_reason: Option<CloseReason>,
+    pub labels: Vec<String>,
+    pub assignee: Option<String>,
+    pub milestone: Option<String>,
+    pub is_pull_request: bool,
+}
+
+/// The open/closed state of a [`SourceIssue`].
+pub enum SourceState {
+    Open,
+    Closed,
+    Other,
+}
+
+/// Why a closed [`SourceIssue`] was closed, where the backend reports it.
+pub enum CloseReason {
+    NotPlanned,
+    Reopened,
+    Duplicate,
+    Completed,
+}
+
+/// A backend-neutral issue event driving an update.
+pub struct SourceEvent {
+    pub id: u64,
+    pub action: String,
+    pub issue: SourceIssue,
+    /// The comment body, for `commented` events.
+    pub comment: Option<String>,
+}
+
+/// An opaque, source-defined poll cursor advanced by
+/// [`IssueSource::poll_events`] and persisted between runs. Each backend
+/// interprets it however it needs to (GitLab stores the newest `updated_at`;
+/// GitHub drives its conditional GET from the disk cache and ignores it).
+#[derive(Default)]
+pub struct EventCursor {
+    pub position: Option<String>,
+}
+
+/// A source of issues and issue events to mirror into YouTrack.
+#[async_trait]
+pub trait IssueSource: Send + Sync {
+    /// Fetch the full current set of issues.
+    async fn list_issues(&self) -> anyhow::Result<Vec<SourceIssue>>;
+
+    /// Fetch the next batch of events, advancing `cursor`.
+    async fn poll_events(&self, cursor: &mut EventCursor) -> anyhow::Result<Vec<SourceEvent>>;
+}
+
+/// The GitHub backend, reading issues and events through a caching,
+/// rate-limit-aware client and fetching comment bodies via octocrab.
+pub struct GitHubSource {
+    octocrab: Octocrab,
+    cache: CachedClient,
+    owner: String,
+    repo: String,
+}
+
+impl GitHubSource {
+    pub fn new(
+        octocrab: Octocrab,
+        cache: CachedClient,
+        owner: String,
+        repo: String,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            octocrab,
+            cache,
+            owner,
+            repo,
+        })
+    }
+}
+
+#[async_trait]
+impl IssueSource for GitHubSource {
+    async fn list_issues(&self) -> anyhow::Result<Vec<SourceIssue>> {
+        let url = api_url(&format!(
+            "/repos/{}/{}/issues?state=all&per_page=100",
+            self.owner, self.repo
+        ))?;
+
+        let body = self.cache.get_all(url.as_str()).await?;
+        let issues: Vec<Issue> =
+            serde_json::from_str(&body).with_context(|| "Failed to parse issues")?;
+
+        Ok(issues.into_iter().map(SourceIssue::from).collect())
+    }
+
+    async fn poll_events(&self, _cursor: &mut EventCursor) -> anyhow::Result<Vec<SourceEvent>> {
+        let url = api_url(&format!(
+            "/repos/{}/{}/issues/events?per_page=10",
+            self.owner, self.repo
+        ))?;
+
+        // The cache serves a 304 straight from disk, so there's nothing new.
+        let page = self.cache.get(url.as_str()).await?;
+        if page.not_modified {
+            return Ok(Vec::new());
+        }
+
+        let raw: Vec<AltEvent> =
+            serde_json::from_str(&page.body).with_context(|| "Failed to parse events")?;
+
+        let mut events = Vec::new();
+        let mut commented = HashSet::new();
+        for event in raw {
+            // The events feed never carries `commented` itself, so for each
+            // issue touched this batch fetch its comments and mirror the newest
+            // one, keyed on the comment id so `seen` dedups it rather than
+            // re-posting on every subsequent event for the same issue.
+            if commented.insert(event.issue.number) {
+                let first_page = self
+                    .octocrab
+                    .issues(&self.owner, &self.repo)
+                    .list_comments(event.issue.number)
+                    .send()
+                    .await?;
+                let comments = self.octocrab.all_pages(first_page).await?;
+                if let Some(comment) = comments.last() {
+                    if let Some(body) = &comment.body {
+                        events.push(SourceEvent {
+                            id: comment.id.0,
+                            action: "commented".to_string(),
+                            issue: SourceIssue::from(event.issue.clone()),
+                            comment: Some(body.clone()),
+                        });
+                    }
+                }
+            }
+
+            events.push(SourceEvent {
+                id: event.id.0,
+                action: event.event,
+                issue: SourceIssue::from(event.issue),
+                comment: None,
+            });
+        }
+
+        Ok(events)
+    }
+}
+
+impl From<octocrab::models::issues::Issue> for SourceIssue {
+    fn from(issue: octocrab::models::issues::Issue) -> Self {
+        let close_reason = issue.state_reason.map(|reason| match reason {
+            IssueStateReason::NotPlanned => CloseReason::NotPlanned,
+            IssueStateReason::Reopened => CloseReason::Reopened,
+            IssueStateReason::Duplicate => CloseReason::Duplicate,
+            _ => CloseReason::Completed,
+        });
+
+        Self {
+            id: issue.id.0,
+            number: issue.number,
+            title: issue.title,
+            body: issue.body,
+            state: match issue.state {
+                IssueState::Open => SourceState::Open,
+                IssueState::Closed => SourceState::Closed,
+                _ => SourceState::Other,
+            },
+            close_reason,
+            labels: issue.labels.into_iter().map(|label| label.name).collect(),
+            assignee: issue.assignee.map(|user| user.login),
+            milestone: issue.milestone.map(|milestone| milestone.title),
+            is_pull_request: issue.pull_request.is_some(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AltEvent {
+    id: octocrab::models::IssueEventId,
+    issue: octocrab::models::issues::Issue,
+    event: String,
+}
+
+/// The GitLab backend, targeting the REST API of a (self-hosted) instance.
+pub struct GitLabSource {
+    client: Client,
+    host: Url,
+    project: String,
+}
+
+impl GitLabSource {
+    pub fn new(host: Url, project: String, token: &str) -> anyhow::Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert("PRIVATE-TOKEN", token.parse()?);
+
+        Ok(Self {
+            client: Client::builder().default_headers(headers).build()?,
+            host,
+            project: project.replace('/', "%2F"),
+        })
+    }
+
+    fn issues_url(&self) -> anyhow::Result<Url> {
+        self.host
+            .join(&format!("api/v4/projects/{}/issues", self.project))
+            .with_context(|| "Failed to build the GitLab issues URL")
+    }
+}
+
+#[async_trait]
+impl IssueSource for GitLabSource {
+    async fn list_issues(&self) -> anyhow::Result<Vec<SourceIssue>> {
+        let mut issues = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let batch = self
+                .client
+                .get(self.issues_url()?)
+                .query(&[("per_page", "100".to_string()), ("page", page.to_string())])
+                .send()
+                .await?
+                .json::<Vec<GitLabIssue>>()
+                .await?;
+            let last = batch.len() < 100;
+            issues.extend(batch);
+            if last {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(issues.into_iter().map(SourceIssue::from).collect())
+    }
+
+    async fn poll_events(&self, cursor: &mut EventCursor) -> anyhow::Result<Vec<SourceEvent>> {
+        // GitLab has no direct equivalent of GitHub's events feed, so poll the
+        // issues ordered by update time and emit an update for everything
+        // touched since the last cursor. The cursor stores the newest
+        // `updated_at` we have observed.
+        let mut query = vec![
+            ("order_by".to_string(), "updated_at".to_string()),
+            ("sort".to_string(), "desc".to_string()),
+            ("per_page".to_string(), "100".to_string()),
+        ];
+        if let Some(updated_after) = &cursor.position {
+            query.push(("updated_after".to_string(), updated_after.clone()));
+        }
+
+        let issues = self
+            .client
+            .get(self.issues_url()?)
+            .query(&query)
+            .send()
+            .await?
+            .json::<Vec<GitLabIssue>>()
+            .await?;
+
+        if let Some(newest) = issues.first().map(|issue| issue.updated_at.clone()) {
+            cursor.position = Some(newest);
+        }
+
+        Ok(issues
+            .into_iter()
+            .map(|issue| SourceEvent {
+                // `updated_after` is inclusive, so the newest issue comes back
+                // on every poll. Key the event on `(id, updated_at)` so an
+                // unchanged issue dedups through `seen` while a genuine edit —
+                // which bumps `updated_at` — produces a fresh key.
+                id: change_key(issue.id, &issue.updated_at),
+                // A full rewrite reconciles whatever changed.
+                action: "renamed".to_string(),
+                issue: SourceIssue::from(issue),
+                comment: None,
+            })
+            .collect())
+    }
+}
+
+/// A stable event id for a `(issue id, updated_at)` pair.
+fn change_key(id: u64, updated_at: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    updated_at.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(Deserialize)]
+struct GitLabIssue {
+    id: u64,
+    iid: u64,
+    title: String,
+    description: Option<String>,
+    state: String,
+    updated_at: String,
+    #[serde(default)]
+    labels: Vec<String>,
+    assignee: Option<GitLabUser>,
+    milestone: Option<GitLabMilestone>,
+}
+
+#[derive(Deserialize)]
+struct GitLabUser {
+    username: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabMilestone {
+    title: String,
+}
+
+impl From<GitLabIssue> for SourceIssue {
+    fn from(issue: GitLabIssue) -> Self {
+        Self {
+            id: issue.id,
+            number: issue.iid,
+            title: issue.title,
+            body: issue.description,
+            state: match issue.state.as_str() {
+                "opened" => SourceState::Open,
+                "closed" => SourceState::Closed,
+                _ => SourceState::Other,
+            },
+            close_reason: None,
+            labels: issue.labels,
+            assignee: issue.assignee.map(|user| user.username),
+            milestone: issue.milestone.map(|milestone| milestone.title),
+            is_pull_request: false,
+        }
+    }
+}
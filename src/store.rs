@@ -0,0 +1,116 @@
+use std::{collections::HashMap, path::Path};
+
+use rusqlite::Connection;
+
+/// Persistent state backing the sync so restarts are idempotent and resumable.
+///
+/// Holds the source issue id → YouTrack [`youtrack_api::IssueId`] mapping along
+/// with the cursor state (the source poll cursor and the `seen` event-id ring
+/// buffer) that the poll loop relies on.
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Open (creating if necessary) the SQLite store at `path`.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS issue_map (
+                 source_id   INTEGER PRIMARY KEY,
+                 youtrack_id TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS meta (
+                 key   TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );",
+        )?;
+
+        Ok(Self { conn })
+    }
+
+    /// Load the full source → YouTrack issue mapping.
+    pub fn load_mapping(&self) -> rusqlite::Result<HashMap<u64, youtrack_api::IssueId>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT source_id, youtrack_id FROM issue_map")?;
+
+        let rows = stmt.query_map([], |row| {
+            let source: u64 = row.get(0)?;
+            let youtrack: String = row.get(1)?;
+            Ok((source, youtrack_api::IssueId::new(youtrack)))
+        })?;
+
+        rows.collect()
+    }
+
+    /// Record a newly created mapping.
+    pub fn insert_mapping(
+        &self,
+        source_id: u64,
+        youtrack_id: &youtrack_api::IssueId,
+    ) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO issue_map (source_id, youtrack_id) VALUES (?1, ?2)",
+            (source_id, youtrack_id.as_str()),
+        )?;
+
+        Ok(())
+    }
+
+    /// The persisted source poll cursor, if any.
+    pub fn cursor(&self) -> rusqlite::Result<Option<String>> {
+        self.get_meta("cursor")
+    }
+
+    /// Persist the source poll cursor after a poll.
+    pub fn set_cursor(&self, cursor: &str) -> rusqlite::Result<()> {
+        self.set_meta("cursor", cursor)
+    }
+
+    /// The persisted `seen` event-id ring buffer, oldest first.
+    pub fn load_seen(&self) -> rusqlite::Result<Vec<u64>> {
+        let Some(raw) = self.get_meta("seen")? else {
+            return Ok(Vec::new());
+        };
+
+        Ok(raw
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| s.parse::<u64>().ok())
+            .collect())
+    }
+
+    /// Persist the `seen` event-id ring buffer.
+    pub fn set_seen(&self, seen: &[u64]) -> rusqlite::Result<()> {
+        let raw = seen
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        self.set_meta("seen", &raw)
+    }
+
+    fn get_meta(&self, key: &str) -> rusqlite::Result<Option<String>> {
+        self.conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .map(Some)
+            .or_else(|err| match err {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                other => Err(other),
+            })
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+            (key, value),
+        )?;
+
+        Ok(())
+    }
+}
@@ -151,6 +151,29 @@ impl Project {
         Ok(response.json::<CreateIssueResponse>().await?.id)
     }
 
+    pub async fn add_comment(&self, issue_id: &IssueId, body: &str) -> Result<()> {
+        #[derive(Serialize)]
+        struct AddComment<'r> {
+            text: &'r str,
+        }
+
+        self.youtrack
+            .client
+            .post(
+                self.youtrack
+                    .host
+                    .join("api/issues/")
+                    .unwrap()
+                    .join(&format!("{}/comments", issue_id.0))
+                    .unwrap(),
+            )
+            .json(&AddComment { text: body })
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn update_issue(&self, issue_id: &IssueId, issue: &IssueData) -> Result<()> {
         self.youtrack
             .client
@@ -182,17 +205,65 @@ pub struct IssueData {
 #[derive(Serialize, Deserialize, Clone)]
 pub struct IssueId(String);
 
+impl IssueId {
+    #[must_use]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 #[derive(Serialize)]
 pub struct CustomField {
     pub name: String,
     #[serde(rename = "$type")]
     pub type_: String,
-    /// This should either be an enum or custom field should be generic
-    /// but that's outside the scope
-    pub value: StateBundleElement,
+    pub value: CustomFieldValue,
+}
+
+/// The value of a YouTrack custom field.
+///
+/// Serialized untagged so each variant produces the shape YouTrack expects for
+/// the matching field kind (a bundle element object, an array of them for
+/// multi-value fields, a user reference, or a bare scalar).
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum CustomFieldValue {
+    State(StateBundleElement),
+    Enum(EnumBundleElement),
+    MultiEnum(Vec<EnumBundleElement>),
+    User(SingleUserValue),
+    Text(String),
+    Number(f64),
 }
 
 #[derive(Serialize)]
 pub struct StateBundleElement {
     pub name: String,
 }
+
+#[derive(Serialize)]
+pub struct EnumBundleElement {
+    pub name: String,
+}
+
+#[derive(Serialize)]
+pub struct SingleUserValue {
+    #[serde(rename = "$type")]
+    pub type_: String,
+    pub login: String,
+}
+
+impl SingleUserValue {
+    #[must_use]
+    pub fn new(login: impl Into<String>) -> Self {
+        Self {
+            type_: "User".to_string(),
+            login: login.into(),
+        }
+    }
+}